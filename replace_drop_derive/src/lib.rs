@@ -0,0 +1,218 @@
+//! Derive macro for `replace_drop::ReplaceDropImpl`
+//!
+//! Generating the impl by hand means writing one `std::ptr::drop_in_place` per
+//! field: forget one and you leak, write it twice and you get UB. This derive
+//! emits the field drop glue for you, in the same declaration order Rust's own
+//! drop glue uses, after an optional user hook.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Index, Meta, Path, parse_macro_input};
+
+/// Derives `ReplaceDropImpl` for a struct.
+///
+/// The generated `drop` first runs the hook named by a container
+/// `#[replace_drop(hook = path)]` attribute (if any), then drops each field in
+/// declaration order via `std::ptr::drop_in_place`. Per-field attributes tune
+/// this: `#[replace_drop(skip)]` suppresses a field's destructor and
+/// `#[replace_drop(replace)]` routes the field through `ReplaceDropImpl::drop`
+/// instead of its normal `Drop`.
+///
+/// Adding `#[replace_drop(unwind_safe)]` to the container wraps the field drops
+/// in a guard so that if one field's destructor panics, the remaining fields
+/// are still dropped while unwinding — each field is dropped exactly once.
+#[proc_macro_derive(ReplaceDropImpl, attributes(replace_drop))]
+pub fn derive_replace_drop_impl(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let config = match parse_container(&input) {
+        Ok(config) => config,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        Data::Enum(_) | Data::Union(_) => {
+            return syn::Error::new_spanned(
+                &input,
+                "ReplaceDropImpl can only be derived for structs",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+
+    let drops = match field_drops(fields) {
+        Ok(drops) => drops,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let body = if config.unwind_safe {
+        // The hook runs inside the guarded region so a panic in the hook still
+        // cleans up every field during unwinding.
+        let hook_call = config.hook.map(|path| quote!(#path(&mut *__ptr);));
+        unwind_safe_body(&drops, hook_call)
+    } else {
+        let hook_call = config.hook.map(|path| quote!(#path(self);));
+        let stmts = drops.iter().map(|d| d.on(&quote!(self)));
+        quote!(#hook_call #(#stmts)*)
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        // SAFETY: the generated body drops each non-skipped field exactly once,
+        // matching the drop glue the compiler would otherwise run.
+        unsafe impl #impl_generics ::replace_drop::ReplaceDropImpl for #name #ty_generics #where_clause {
+            unsafe fn drop(&mut self) {
+                #body
+            }
+        }
+    }
+    .into()
+}
+
+/// Emits the guarded drop sequence used by `unwind_safe` mode.
+///
+/// `stage` is bumped to `i + 1` *before* field `i` is dropped, so if that drop
+/// panics the guard resumes from field `i + 1` — the panicking field is
+/// considered consumed and is never dropped twice.
+fn unwind_safe_body(drops: &[FieldDrop], hook_call: Option<TokenStream2>) -> TokenStream2 {
+    let base = quote!((*__ptr));
+    let resume = drops.iter().enumerate().map(|(i, d)| {
+        let stmt = d.on(&base);
+        quote!(if __stage <= #i { #stmt })
+    });
+    let run = drops.iter().enumerate().map(|(i, d)| {
+        let next = i + 1;
+        let stmt = d.on(&base);
+        quote! {
+            __guard.stage = #next;
+            #stmt
+        }
+    });
+
+    quote! {
+        let __ptr: *mut Self = self;
+        let __resume: unsafe fn(*mut Self, usize) = |__ptr, __stage| {
+            #(#resume)*
+        };
+        let mut __guard = ::replace_drop::__FieldDropGuard {
+            ptr: __ptr,
+            drop_from: __resume,
+            stage: 0,
+        };
+        // Run the hook inside the guarded region: if it panics, the guard still
+        // drops every field (stage is 0, so none are treated as consumed).
+        #hook_call
+        #(#run)*
+        // Fall through with stage == len: the guard's Drop runs nothing.
+    }
+}
+
+struct Container {
+    hook: Option<Path>,
+    unwind_safe: bool,
+}
+
+/// Parses the container `#[replace_drop(...)]` attributes.
+fn parse_container(input: &DeriveInput) -> syn::Result<Container> {
+    let mut hook = None;
+    let mut unwind_safe = false;
+    for attr in &input.attrs {
+        if !attr.path().is_ident("replace_drop") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("hook") {
+                hook = Some(meta.value()?.parse::<Path>()?);
+                Ok(())
+            } else if meta.path.is_ident("unwind_safe") {
+                unwind_safe = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported replace_drop container attribute"))
+            }
+        })?;
+    }
+    Ok(Container { hook, unwind_safe })
+}
+
+/// A single field's drop mode together with the tokens needed to reach it.
+struct FieldDrop {
+    access: TokenStream2,
+    mode: FieldMode,
+}
+
+impl FieldDrop {
+    /// Renders the drop statement with `base` as the receiver (`self` or `(*__ptr)`).
+    fn on(&self, base: &TokenStream2) -> TokenStream2 {
+        let access = &self.access;
+        match self.mode {
+            // SAFETY: `&mut` is always a valid, aligned, initialized pointer.
+            FieldMode::Normal => quote!(::std::ptr::drop_in_place(&mut #base.#access as *mut _);),
+            FieldMode::Replace => {
+                quote!(::replace_drop::ReplaceDropImpl::drop(&mut #base.#access);)
+            }
+            FieldMode::Skip => unreachable!("skipped fields are filtered out"),
+        }
+    }
+}
+
+/// Builds the per-field drop descriptors in declaration order, dropping skips.
+fn field_drops(fields: &Fields) -> syn::Result<Vec<FieldDrop>> {
+    let mut drops = Vec::new();
+    for (idx, field) in fields.iter().enumerate() {
+        let mode = parse_field_mode(field)?;
+        if matches!(mode, FieldMode::Skip) {
+            continue;
+        }
+
+        let access = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = Index::from(idx);
+                quote!(#index)
+            }
+        };
+
+        drops.push(FieldDrop { access, mode });
+    }
+    Ok(drops)
+}
+
+enum FieldMode {
+    Normal,
+    Skip,
+    Replace,
+}
+
+/// Reads the optional `#[replace_drop(skip)]` / `#[replace_drop(replace)]` on a field.
+fn parse_field_mode(field: &syn::Field) -> syn::Result<FieldMode> {
+    let mut mode = FieldMode::Normal;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("replace_drop") {
+            continue;
+        }
+        if let Meta::Path(_) = attr.meta {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "expected `skip` or `replace`, e.g. #[replace_drop(skip)]",
+            ));
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                mode = FieldMode::Skip;
+                Ok(())
+            } else if meta.path.is_ident("replace") {
+                mode = FieldMode::Replace;
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip` or `replace`"))
+            }
+        })?;
+    }
+    Ok(mode)
+}
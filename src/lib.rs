@@ -8,6 +8,35 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
+/// Derives [`ReplaceDropImpl`] for a struct, generating the field drop glue so
+/// you don't have to write a `std::ptr::drop_in_place` per field by hand.
+///
+/// The generated `drop` runs an optional `#[replace_drop(hook = path)]` function
+/// first, then drops each field in declaration order. `#[replace_drop(skip)]`
+/// suppresses a field's destructor and `#[replace_drop(replace)]` routes it
+/// through [`ReplaceDropImpl::drop`] instead of its normal `Drop`.
+///
+/// ```
+/// use replace_drop::{ReplaceDrop, ReplaceDropImpl};
+///
+/// fn before(outer: &mut Outer) {
+///     println!("hook");
+/// }
+///
+/// #[derive(ReplaceDropImpl)]
+/// #[replace_drop(hook = before)]
+/// struct Outer {
+///     data: String,
+///     #[replace_drop(skip)]
+///     cached: i32,
+/// }
+///
+/// # fn main() {
+/// let _ = ReplaceDrop::new(Outer { data: "hi".into(), cached: 3 });
+/// # }
+/// ```
+pub use replace_drop_derive::ReplaceDropImpl;
+
 /// # Safety
 /// The implemenentor must ensure that they do not remove any drop functionality that is important
 /// When using ReplaceDrop, the struct's fields to not automatically get dropped
@@ -64,6 +93,34 @@ impl<T: ReplaceDropImpl> ReplaceDrop<T> {
         std::mem::forget(self);
         val
     }
+
+    /// The drop-inhibited sibling of [`into_inner`](ReplaceDrop::into_inner):
+    /// hands back the still-wrapped `ManuallyDrop<T>` without running the
+    /// replacement drop, for interop with `ManuallyDrop`-based code.
+    pub fn into_manually_drop(self) -> ManuallyDrop<T> {
+        // SAFETY: We read the inner value out and forget self, so its Drop
+        // (and therefore the replacement drop) never runs.
+        let inner = unsafe { std::ptr::read(&self.0) };
+        std::mem::forget(self);
+        inner
+    }
+
+    /// Takes the value out without running the replacement drop, mirroring
+    /// [`ManuallyDrop::take`].
+    ///
+    /// # Safety
+    /// After calling this the wrapper must not be used again, and its `Drop`
+    /// must be prevented (e.g. via [`std::mem::forget`]), otherwise the
+    /// replacement drop would run on a value that has been moved out.
+    pub unsafe fn take(&mut self) -> T {
+        unsafe { ManuallyDrop::take(&mut self.0) }
+    }
+
+    /// Borrows the inner `ManuallyDrop<T>` for interop with
+    /// `ManuallyDrop`-based drop-order control and partial moves.
+    pub fn as_manually_drop(&self) -> &ManuallyDrop<T> {
+        &self.0
+    }
 }
 
 impl<T: ReplaceDropImpl> Drop for ReplaceDrop<T> {
@@ -111,6 +168,220 @@ pub fn replace_drop<T: ReplaceDropImpl>(val: T) {
     let _ = ReplaceDrop::new(val);
 }
 
+/// Implementation detail of `#[derive(ReplaceDropImpl)]`'s `unwind_safe` mode.
+///
+/// The derived `drop` advances `stage` as it drops each field. If a field
+/// destructor panics, this guard's own `Drop` calls `drop_from` with the
+/// current `stage`, dropping the fields that had not been reached yet so every
+/// field is dropped exactly once even while unwinding. Not part of the public
+/// API and subject to change.
+#[doc(hidden)]
+pub struct __FieldDropGuard<T> {
+    pub ptr: *mut T,
+    pub drop_from: unsafe fn(*mut T, usize),
+    pub stage: usize,
+}
+
+impl<T> Drop for __FieldDropGuard<T> {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` came from a live `&mut T` and no other reference to it
+        // is alive while this guard runs during unwinding.
+        unsafe { (self.drop_from)(self.ptr, self.stage) };
+    }
+}
+
+/// The owned counterpart to [`ReplaceDropImpl`]: the replacement destructor
+/// receives the value *by value* instead of `&mut self`.
+///
+/// `Drop::drop` only hands out `&mut self`, so moving a field out of it normally
+/// forces the `Option`-wrap-and-`unwrap` dance. Because [`ReplaceDropOwned`] owns
+/// the value through `ManuallyDrop`, it can hand the whole value to this trait,
+/// letting you `mem::replace`/move fields out with no `Option` and no unsafe in
+/// your own code.
+///
+/// # Safety
+/// The implementor must ensure they do not remove any drop functionality that is
+/// important. As with [`ReplaceDropImpl`], the value's fields are not dropped
+/// automatically.
+pub unsafe trait ReplaceDropOwnedImpl: Sized {
+    fn drop(self);
+}
+
+// SAFETY: Unit type does not have a default drop
+unsafe impl ReplaceDropOwnedImpl for () {
+    fn drop(self) {}
+}
+
+/// A wrapper like [`ReplaceDrop`] that calls [`ReplaceDropOwnedImpl::drop`],
+/// handing the owned value to the replacement destructor.
+/// Example:
+/// ```
+/// use replace_drop::{ReplaceDropOwnedImpl, ReplaceDropOwned};
+/// struct MyData { data: String }
+/// unsafe impl ReplaceDropOwnedImpl for MyData {
+///     fn drop(self) {
+///         // We own `self`, so we can move fields out freely.
+///         let data = self.data;
+///         println!("Called drop with {data}")
+///     }
+/// }
+///
+/// # fn main() {
+/// let data = MyData { data: "hello".into() };
+/// drop(ReplaceDropOwned::new(data)); // Prints "Called drop with hello"
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReplaceDropOwned<T: ReplaceDropOwnedImpl>(ManuallyDrop<T>);
+
+impl<T: ReplaceDropOwnedImpl> ReplaceDropOwned<T> {
+    #[must_use = "use `replace_drop::replace_drop_owned` to clarify the intent: replace_drop_owned(val);"]
+    pub fn new(val: T) -> Self {
+        ReplaceDropOwned(ManuallyDrop::new(val))
+    }
+
+    #[must_use = "use `replace_drop::replace_drop_owned` to clarify the intent: replace_drop_owned(val);"]
+    pub fn new_from_manually_drop(val: ManuallyDrop<T>) -> Self {
+        ReplaceDropOwned(val)
+    }
+
+    pub fn into_inner(mut self) -> T {
+        // SAFETY: We immediatly mem::forget(self) after this so self.0 cant be used
+        let val = unsafe { ManuallyDrop::take(&mut self.0) };
+        std::mem::forget(self);
+        val
+    }
+}
+
+impl<T: ReplaceDropOwnedImpl> Drop for ReplaceDropOwned<T> {
+    fn drop(&mut self) {
+        // SAFETY: This is called in the Drop implementation, so the value is
+        // taken exactly once and self.0 is never touched again.
+        let val = unsafe { ManuallyDrop::take(&mut self.0) };
+        <T as ReplaceDropOwnedImpl>::drop(val);
+    }
+}
+
+impl<T: ReplaceDropOwnedImpl> Deref for ReplaceDropOwned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: ReplaceDropOwnedImpl> DerefMut for ReplaceDropOwned<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Works like drop(val) but uses the [`ReplaceDropOwnedImpl`]
+/// Example:
+/// ```
+/// use replace_drop::{ReplaceDropOwnedImpl, replace_drop_owned};
+/// struct MyData { data: String }
+/// unsafe impl ReplaceDropOwnedImpl for MyData {
+///     fn drop(self) {
+///         println!("Called replace_drop_owned with {}", self.data)
+///     }
+/// }
+///
+/// # fn main() {
+/// replace_drop_owned(MyData { data: "hello".into() }); // Prints "Called replace_drop_owned with hello"
+/// # }
+/// ```
+pub fn replace_drop_owned<T: ReplaceDropOwnedImpl>(val: T) {
+    let _ = ReplaceDropOwned::new(val);
+}
+
+/// A scope guard that runs a closure, then drops the value.
+///
+/// The closure acts as a pre-drop hook — it runs first, then `T`'s own
+/// destructor runs — mirroring the derive's hook-then-fields model.
+///
+/// Unlike [`ReplaceDrop`] this needs no `ReplaceDropImpl` impl: the cleanup
+/// logic lives in an `FnOnce(&mut T)` stored alongside the value, so it covers
+/// the common "run this exactly once at end of scope unless I cancel it" case
+/// without a new type and trait impl per behavior. Defuse it with
+/// [`into_inner`](ReplaceDropGuard::into_inner), which returns the value
+/// unchanged without running the closure.
+///
+/// This is a standalone type rather than a `ReplaceDrop::guard` constructor:
+/// [`ReplaceDrop`] is bound by `T: ReplaceDropImpl`, but the scope guard must
+/// work for any `T`, so it cannot live on that type. Use [`replace_drop_with`]
+/// as the constructor.
+/// Example:
+/// ```
+/// use replace_drop::replace_drop_with;
+/// let mut ran = false;
+/// {
+///     let _guard = replace_drop_with(&mut ran, |ran| **ran = true);
+/// }
+/// assert!(ran);
+/// ```
+pub struct ReplaceDropGuard<T, F: FnOnce(&mut T)> {
+    value: ManuallyDrop<T>,
+    dropfn: Option<F>,
+}
+
+impl<T, F: FnOnce(&mut T)> ReplaceDropGuard<T, F> {
+    #[must_use = "the guard runs its closure when dropped; bind it to a name to keep it alive"]
+    pub fn new(value: T, dropfn: F) -> Self {
+        ReplaceDropGuard {
+            value: ManuallyDrop::new(value),
+            dropfn: Some(dropfn),
+        }
+    }
+
+    /// Defuses the guard and returns the value unchanged, without running the closure.
+    pub fn into_inner(mut self) -> T {
+        // SAFETY: We immediatly mem::forget(self) after this so self.value cant be used
+        let val = unsafe { ManuallyDrop::take(&mut self.value) };
+        std::mem::forget(self);
+        val
+    }
+}
+
+impl<T, F: FnOnce(&mut T)> Drop for ReplaceDropGuard<T, F> {
+    fn drop(&mut self) {
+        if let Some(dropfn) = self.dropfn.take() {
+            dropfn(&mut self.value);
+        }
+        // SAFETY: This is the Drop impl, so the value is dropped exactly once
+        // and self.value is never touched again.
+        unsafe { ManuallyDrop::drop(&mut self.value) };
+    }
+}
+
+impl<T, F: FnOnce(&mut T)> Deref for ReplaceDropGuard<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T, F: FnOnce(&mut T)> DerefMut for ReplaceDropGuard<T, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+/// Stores `value` together with `dropfn` and runs the closure, then drops the
+/// value, unless the returned [`ReplaceDropGuard`] is defused with
+/// [`into_inner`](ReplaceDropGuard::into_inner).
+/// Example:
+/// ```
+/// use replace_drop::replace_drop_with;
+/// let guard = replace_drop_with(String::from("hi"), |s| println!("cleaning up {s}"));
+/// let value = guard.into_inner(); // prints nothing, hands the String back
+/// assert_eq!(value, "hi");
+/// ```
+pub fn replace_drop_with<T, F: FnOnce(&mut T)>(value: T, dropfn: F) -> ReplaceDropGuard<T, F> {
+    ReplaceDropGuard::new(value, dropfn)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,4 +414,63 @@ mod tests {
 
         assert_eq!(t, 5);
     }
+
+    #[test]
+    fn test_owned() {
+        struct MyType(String);
+        unsafe impl ReplaceDropOwnedImpl for MyType {
+            fn drop(self) {
+                // We own the value, so we can move the field out without Option.
+                let s = self.0;
+                assert_eq!(s, "moved");
+            }
+        }
+
+        replace_drop_owned(MyType("moved".to_string()));
+
+        let kept = ReplaceDropOwned::new(MyType("kept".to_string())).into_inner();
+        assert_eq!(kept.0, "kept");
+    }
+
+    #[test]
+    fn test_guard() {
+        let mut cleaned = 0;
+        drop(replace_drop_with(&mut cleaned, |c| **c = 1));
+        assert_eq!(cleaned, 1);
+
+        let mut cleaned = 0;
+        let value = replace_drop_with(&mut cleaned, |c| **c = 1).into_inner();
+        *value = 7;
+        assert_eq!(cleaned, 7);
+    }
+
+    #[test]
+    fn test_manually_drop_interop() {
+        struct MyType<'a>(&'a mut u32);
+        impl<'a> Drop for MyType<'a> {
+            fn drop(&mut self) {
+                *self.0 = 1;
+            }
+        }
+        unsafe impl<'a> ReplaceDropImpl for MyType<'a> {
+            unsafe fn drop(&mut self) {
+                *self.0 = 5;
+            }
+        }
+
+        let mut t = 0;
+
+        // into_manually_drop hands back a drop-inhibited value: neither drop runs.
+        let md = ReplaceDrop::new(MyType(&mut t)).into_manually_drop();
+        assert_eq!(*md.0, 0);
+        let _ = md; // ManuallyDrop already inhibits the inner drop
+        assert_eq!(t, 0);
+
+        // take moves the value out; the returned MyType runs its normal Drop.
+        let mut rd = ReplaceDrop::new(MyType(&mut t));
+        let inner = unsafe { rd.take() };
+        std::mem::forget(rd);
+        drop(inner);
+        assert_eq!(t, 1);
+    }
 }
@@ -0,0 +1,43 @@
+use std::cell::RefCell;
+
+use replace_drop::{ReplaceDrop, ReplaceDropImpl};
+
+thread_local! {
+    static LOG: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(name: &'static str) {
+    LOG.with(|l| l.borrow_mut().push(name));
+}
+
+/// A field with both a normal `Drop` and a `ReplaceDropImpl`, so we can tell
+/// which one the derive routed through.
+struct Field;
+
+impl Drop for Field {
+    fn drop(&mut self) {
+        record("normal");
+    }
+}
+
+unsafe impl ReplaceDropImpl for Field {
+    unsafe fn drop(&mut self) {
+        record("replace");
+    }
+}
+
+#[derive(ReplaceDropImpl)]
+struct Outer {
+    #[replace_drop(replace)]
+    field: Field,
+}
+
+#[test]
+fn replace_field_routes_through_replace_drop_impl() {
+    LOG.with(|l| l.borrow_mut().clear());
+
+    drop(ReplaceDrop::new(Outer { field: Field }));
+
+    // The field went through ReplaceDropImpl::drop, not its normal Drop.
+    LOG.with(|l| assert_eq!(*l.borrow(), ["replace"]));
+}
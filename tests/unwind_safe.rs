@@ -0,0 +1,92 @@
+use std::cell::RefCell;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use replace_drop::{ReplaceDrop, ReplaceDropImpl};
+
+thread_local! {
+    static DROPS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(name: &'static str) {
+    DROPS.with(|d| d.borrow_mut().push(name));
+}
+
+/// A field whose destructor optionally panics, recording that it ran either way.
+struct Noisy {
+    name: &'static str,
+    panic: bool,
+}
+
+impl Drop for Noisy {
+    fn drop(&mut self) {
+        record(self.name);
+        if self.panic {
+            panic!("boom in {}", self.name);
+        }
+    }
+}
+
+#[derive(ReplaceDropImpl)]
+#[replace_drop(unwind_safe)]
+struct Three {
+    a: Noisy,
+    b: Noisy,
+    c: Noisy,
+}
+
+fn panic_hook(_: &mut Hooked) {
+    panic!("boom in hook");
+}
+
+#[derive(ReplaceDropImpl)]
+#[replace_drop(unwind_safe, hook = panic_hook)]
+struct Hooked {
+    a: Noisy,
+    b: Noisy,
+}
+
+#[test]
+fn each_field_dropped_once_on_normal_return() {
+    DROPS.with(|d| d.borrow_mut().clear());
+
+    drop(ReplaceDrop::new(Three {
+        a: Noisy { name: "a", panic: false },
+        b: Noisy { name: "b", panic: false },
+        c: Noisy { name: "c", panic: false },
+    }));
+
+    DROPS.with(|d| assert_eq!(*d.borrow(), ["a", "b", "c"]));
+}
+
+#[test]
+fn remaining_fields_dropped_once_when_a_field_panics() {
+    DROPS.with(|d| d.borrow_mut().clear());
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        drop(ReplaceDrop::new(Three {
+            a: Noisy { name: "a", panic: false },
+            b: Noisy { name: "b", panic: true },
+            c: Noisy { name: "c", panic: false },
+        }));
+    }));
+
+    assert!(result.is_err());
+    // `b` panicked mid-drop; the guard still runs `c`. Every field appears once.
+    DROPS.with(|d| assert_eq!(*d.borrow(), ["a", "b", "c"]));
+}
+
+#[test]
+fn all_fields_dropped_once_when_hook_panics() {
+    DROPS.with(|d| d.borrow_mut().clear());
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        drop(ReplaceDrop::new(Hooked {
+            a: Noisy { name: "a", panic: false },
+            b: Noisy { name: "b", panic: false },
+        }));
+    }));
+
+    assert!(result.is_err());
+    // The hook panics before any field is dropped; the guard cleans up both.
+    DROPS.with(|d| assert_eq!(*d.borrow(), ["a", "b"]));
+}